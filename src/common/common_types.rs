@@ -42,6 +42,18 @@ impl Identification {
     pub fn new(label: Lang, prob: f32) -> Self {
         Self { label, prob }
     }
+
+    /// Like [Identification::new], but parses `label` via [Lang::from_str]
+    /// instead of requiring an already-parsed [Lang], returning
+    /// `Err(`[Error::UnknownLang]`)` instead of panicking when the tag isn't
+    /// recognized. Callers batch-processing untrusted labels (e.g. from a
+    /// third-party identifier) can fold that error into their own
+    /// [Diagnostics](crate::common::Diagnostics) instead of aborting.
+    pub fn try_from_label(label: &str, prob: f32) -> Result<Self, Error> {
+        let label = Lang::from_str(label).map_err(|_| Error::UnknownLang(label.to_string()))?;
+        Ok(Self { label, prob })
+    }
+
     /// Get a reference to the identification's label.
     pub fn label(&self) -> &Lang {
         &self.label
@@ -57,4 +69,9 @@ impl Identification {
 mod tests {
 
     use super::Identification;
+
+    #[test]
+    fn test_try_from_label_rejects_unknown_tag() {
+        assert!(Identification::try_from_label("not-a-real-language-tag!!!", 1.0).is_err());
+    }
 }
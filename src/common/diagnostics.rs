@@ -0,0 +1,146 @@
+/*! Non-fatal diagnostics accumulator
+Processing a shard of thousands of documents shouldn't bail on the first
+[Error]: a [Diagnostics] collects per-document problems so a pipeline can log
+them and keep going, while still tracking whether anything fatal happened via
+[Diagnostics::has_error]. Producers that already fold their own recoverable
+[Error]s in here include
+[Document::from_record_with_diagnostics](crate::v3::types::document::Document::from_record_with_diagnostics)
+(body/header UTF-8 recovery) and
+[Identification::try_from_label](crate::common::Identification::try_from_label)
+(unknown language tags, via `Err(`[Error::UnknownLang]`)` for the caller to
+push); a `serde` failure is just the plain `Err(`[Error::Serde]`)` already
+returned by the fallible readers/writers elsewhere in the crate.
+!*/
+use crate::error::Error;
+
+/// Whether a [DiagnosticEntry] stopped processing of its document (`Fatal`)
+/// or was recovered from and processing continued (`Recoverable`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Fatal,
+    Recoverable,
+}
+
+/// A single diagnosed problem, tagged with its [Severity].
+#[derive(Debug)]
+pub struct DiagnosticEntry {
+    error: Error,
+    severity: Severity,
+}
+
+impl DiagnosticEntry {
+    pub fn fatal(error: Error) -> Self {
+        Self {
+            error,
+            severity: Severity::Fatal,
+        }
+    }
+
+    pub fn recoverable(error: Error) -> Self {
+        Self {
+            error,
+            severity: Severity::Recoverable,
+        }
+    }
+
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.severity == Severity::Fatal
+    }
+}
+
+/// An ordered collection of [DiagnosticEntry]s gathered while processing a
+/// batch of documents.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Vec<DiagnosticEntry>,
+    has_error: bool,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a diagnostic, setting [Diagnostics::has_error] if it's fatal.
+    pub fn push(&mut self, entry: DiagnosticEntry) {
+        if entry.is_fatal() {
+            self.has_error = true;
+        }
+        self.entries.push(entry);
+    }
+
+    /// Record every diagnostic yielded by `entries`.
+    pub fn extend<I: IntoIterator<Item = DiagnosticEntry>>(&mut self, entries: I) {
+        for entry in entries {
+            self.push(entry);
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, DiagnosticEntry> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether any recorded diagnostic was [Severity::Fatal].
+    pub fn has_error(&self) -> bool {
+        self.has_error
+    }
+
+    /// Entries that stopped processing.
+    pub fn fatal(&self) -> impl Iterator<Item = &DiagnosticEntry> {
+        self.iter().filter(|e| e.is_fatal())
+    }
+
+    /// Entries that were recovered from.
+    pub fn recoverable(&self) -> impl Iterator<Item = &DiagnosticEntry> {
+        self.iter().filter(|e| !e.is_fatal())
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a DiagnosticEntry;
+    type IntoIter = std::slice::Iter<'a, DiagnosticEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_error_only_on_fatal() {
+        let mut diagnostics = Diagnostics::new();
+        assert!(!diagnostics.has_error());
+
+        diagnostics.push(DiagnosticEntry::recoverable(Error::Custom(
+            "lossy header".to_string(),
+        )));
+        assert!(!diagnostics.has_error());
+        assert_eq!(diagnostics.len(), 1);
+
+        diagnostics.push(DiagnosticEntry::fatal(Error::Custom(
+            "unparseable record".to_string(),
+        )));
+        assert!(diagnostics.has_error());
+        assert_eq!(diagnostics.fatal().count(), 1);
+        assert_eq!(diagnostics.recoverable().count(), 1);
+    }
+}
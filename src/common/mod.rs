@@ -0,0 +1,5 @@
+mod common_types;
+pub mod diagnostics;
+
+pub use common_types::Identification;
+pub use diagnostics::{DiagnosticEntry, Diagnostics, Severity};
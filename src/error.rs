@@ -8,6 +8,7 @@ pub enum Error {
     MetadataConversion(FromUtf8Error),
     Custom(String),
     Serde(serde_json::Error),
+    Avro(apache_avro::Error),
 }
 
 impl From<std::io::Error> for Error {
@@ -33,3 +34,34 @@ impl From<serde_json::Error> for Error {
         Error::Serde(e)
     }
 }
+
+impl From<apache_avro::Error> for Error {
+    fn from(e: apache_avro::Error) -> Error {
+        Error::Avro(e)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {e}"),
+            Error::UnknownLang(lang) => write!(f, "unknown language tag: {lang}"),
+            Error::MetadataConversion(e) => write!(f, "metadata was not valid utf-8: {e}"),
+            Error::Custom(s) => write!(f, "{s}"),
+            Error::Serde(e) => write!(f, "serialization error: {e}"),
+            Error::Avro(e) => write!(f, "avro error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::MetadataConversion(e) => Some(e),
+            Error::Serde(e) => Some(e),
+            Error::Avro(e) => Some(e),
+            Error::UnknownLang(_) | Error::Custom(_) => None,
+        }
+    }
+}
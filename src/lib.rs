@@ -0,0 +1,3 @@
+pub mod common;
+pub mod error;
+pub mod v3;
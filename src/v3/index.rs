@@ -0,0 +1,311 @@
+/*! CDX-style indexing for [Document] stores
+While writing [Document]s out, an [IndexingWriter] compresses each one as its
+own standalone gzip member (multi-stream gzip files are independently
+decompressable member-by-member) and records where it landed. The resulting
+[CdxEntry] lines let a [CdxReader] later seek straight to one document by URL
+or WARC record id instead of scanning the whole store.
+!*/
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use warc::WarcHeader;
+
+use crate::error::Error;
+use crate::v3::types::document::Document;
+
+/// One line of a CDX index: where a single [Document] lives in its store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CdxEntry {
+    /// Target URL of the document (from [WarcHeader::TargetURI]).
+    pub url: String,
+    /// WARC timestamp of the document (from [WarcHeader::Date]).
+    pub timestamp: String,
+    /// WARC record id (from [WarcHeader::RecordID]).
+    pub record_id: String,
+    /// Byte offset of the self-contained gzip member in the store.
+    pub offset: u64,
+    /// Compressed length of that gzip member.
+    pub length: u64,
+}
+
+/// Field separator for the on-disk CDX line format.
+///
+/// A tab is used instead of generic whitespace so that an empty field (e.g.
+/// a document with no `WarcHeader::TargetURI`) still parses back to the
+/// right number of fields instead of silently collapsing and shifting the
+/// rest of the line out of place.
+const CDX_FIELD_SEP: char = '\t';
+
+impl std::fmt::Display for CdxEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{sep}{}{sep}{}{sep}{}{sep}{}",
+            self.url,
+            self.timestamp,
+            self.record_id,
+            self.offset,
+            self.length,
+            sep = CDX_FIELD_SEP
+        )
+    }
+}
+
+impl std::str::FromStr for CdxEntry {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = line.split(CDX_FIELD_SEP).collect();
+        let [url, timestamp, record_id, offset, length]: [&str; 5] =
+            fields.try_into().map_err(|fields: Vec<&str>| {
+                Error::Custom(format!(
+                    "CDX line has {} fields, expected 5: {line}",
+                    fields.len()
+                ))
+            })?;
+
+        let offset = offset
+            .parse()
+            .map_err(|_| Error::Custom(format!("invalid CDX offset: {line}")))?;
+        let length = length
+            .parse()
+            .map_err(|_| Error::Custom(format!("invalid CDX length: {line}")))?;
+
+        Ok(Self {
+            url: url.to_string(),
+            timestamp: timestamp.to_string(),
+            record_id: record_id.to_string(),
+            offset,
+            length,
+        })
+    }
+}
+
+/// An in-memory, queryable CDX index.
+///
+/// `entries` is sorted by `url` lazily, the first time it's needed by a
+/// lookup or by [CdxIndex::write], rather than on every [CdxIndex::push] —
+/// indexing a multi-thousand-document shard one `Vec::insert` at a time
+/// would otherwise be O(n²).
+#[derive(Debug, Clone, Default)]
+pub struct CdxIndex {
+    entries: Vec<CdxEntry>,
+    sorted: bool,
+}
+
+impl CdxIndex {
+    /// Add an entry. O(1) amortized; sorting is deferred to the next lookup or write.
+    pub fn push(&mut self, entry: CdxEntry) {
+        self.entries.push(entry);
+        self.sorted = false;
+    }
+
+    fn ensure_sorted(&mut self) {
+        if !self.sorted {
+            self.entries.sort_by(|a, b| a.url.cmp(&b.url));
+            self.sorted = true;
+        }
+    }
+
+    /// Look up an entry by exact target URL.
+    pub fn find_by_url(&mut self, url: &str) -> Option<&CdxEntry> {
+        self.ensure_sorted();
+        let pos = self.entries.partition_point(|e| e.url.as_str() < url);
+        self.entries.get(pos).filter(|e| e.url == url)
+    }
+
+    /// Look up an entry by WARC record id.
+    pub fn find_by_record_id(&self, record_id: &str) -> Option<&CdxEntry> {
+        self.entries.iter().find(|e| e.record_id == record_id)
+    }
+
+    /// Write the index out, one sorted CDX line per entry.
+    pub fn write<W: Write>(&mut self, mut writer: W) -> Result<(), Error> {
+        self.ensure_sorted();
+        for entry in &self.entries {
+            writeln!(writer, "{entry}")?;
+        }
+        Ok(())
+    }
+
+    /// Parse a CDX index previously produced by [CdxIndex::write].
+    pub fn read<R: Read>(reader: R) -> Result<Self, Error> {
+        use std::io::BufRead;
+        let mut index = Self::default();
+        for line in std::io::BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            index.entries.push(line.parse()?);
+        }
+        index.ensure_sorted();
+        Ok(index)
+    }
+}
+
+/// Wraps a [Write] and emits a [CdxIndex] as [Document]s are appended.
+///
+/// Every document is serialized to JSON and compressed as its own gzip
+/// member, so a [CdxReader] can later fetch and inflate it in isolation
+/// without touching the rest of the store.
+pub struct IndexingWriter<W: Write> {
+    inner: W,
+    offset: u64,
+    index: CdxIndex,
+}
+
+impl<W: Write> IndexingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            index: CdxIndex::default(),
+        }
+    }
+
+    /// Serialize, compress and append `document`, recording its [CdxEntry].
+    pub fn append(&mut self, document: &Document) -> Result<(), Error> {
+        let url = document.url().unwrap_or_default();
+        let timestamp = document
+            .warc_headers()
+            .get(&WarcHeader::Date)
+            .map(|v| String::from_utf8_lossy(v).into_owned())
+            .unwrap_or_default();
+        // Avoid `document.warc_id()`, which panics when `WARC-Record-ID` is
+        // absent; an indexer must not crash on one malformed record.
+        let record_id = document
+            .warc_headers()
+            .get(&WarcHeader::RecordID)
+            .map(|v| String::from_utf8_lossy(v).into_owned())
+            .unwrap_or_default();
+
+        let json = serde_json::to_vec(document)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        let member = encoder.finish()?;
+
+        self.inner.write_all(&member)?;
+        let length = member.len() as u64;
+
+        self.index.push(CdxEntry {
+            url,
+            timestamp,
+            record_id,
+            offset: self.offset,
+            length,
+        });
+        self.offset += length;
+
+        Ok(())
+    }
+
+    /// The index accumulated so far, ready to be written out via [CdxIndex::write].
+    pub fn index_mut(&mut self) -> &mut CdxIndex {
+        &mut self.index
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Random-access reader pairing a [CdxIndex] with the store it describes.
+pub struct CdxReader<S: Read + Seek> {
+    store: S,
+    index: CdxIndex,
+}
+
+impl<S: Read + Seek> CdxReader<S> {
+    pub fn new(store: S, index: CdxIndex) -> Self {
+        Self { store, index }
+    }
+
+    /// Fetch and decode the [Document] at `url`, seeking directly to its gzip member.
+    pub fn get_by_url(&mut self, url: &str) -> Result<Option<Document>, Error> {
+        let entry = match self.index.find_by_url(url) {
+            Some(entry) => entry.clone(),
+            None => return Ok(None),
+        };
+        self.get_at(&entry).map(Some)
+    }
+
+    /// Fetch and decode the [Document] with the given WARC record id.
+    pub fn get_by_record_id(&mut self, record_id: &str) -> Result<Option<Document>, Error> {
+        let entry = match self.index.find_by_record_id(record_id) {
+            Some(entry) => entry.clone(),
+            None => return Ok(None),
+        };
+        self.get_at(&entry).map(Some)
+    }
+
+    fn get_at(&mut self, entry: &CdxEntry) -> Result<Document, Error> {
+        self.store.seek(SeekFrom::Start(entry.offset))?;
+        let mut member = vec![0u8; entry.length as usize];
+        self.store.read_exact(&mut member)?;
+
+        let mut decoder = GzDecoder::new(member.as_slice());
+        let mut json = Vec::new();
+        decoder.read_to_end(&mut json)?;
+
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3::types::document::{Document, Metadata};
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_cdx_entry_roundtrip_with_empty_url() {
+        let entry = CdxEntry {
+            url: String::new(),
+            timestamp: String::new(),
+            record_id: "<urn:uuid:1>".to_string(),
+            offset: 42,
+            length: 7,
+        };
+
+        let parsed = CdxEntry::from_str(&entry.to_string()).unwrap();
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn test_indexing_writer_does_not_panic_without_record_id() {
+        let document = Document::new(Default::default(), Default::default(), Metadata::default());
+
+        let mut writer = IndexingWriter::new(Vec::new());
+        writer.append(&document).unwrap();
+        assert_eq!(writer.index_mut().find_by_record_id("").map(|e| &e.record_id), Some(&String::new()));
+    }
+
+    #[test]
+    fn test_cdx_index_sorts_lazily_but_correctly() {
+        let mut index = CdxIndex::default();
+        for url in ["c", "a", "b"] {
+            index.push(CdxEntry {
+                url: url.to_string(),
+                timestamp: String::new(),
+                record_id: String::new(),
+                offset: 0,
+                length: 0,
+            });
+        }
+
+        let mut out = Vec::new();
+        index.write(&mut out).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+        let urls: Vec<&str> = lines.iter().map(|l| l.split('\t').next().unwrap()).collect();
+        assert_eq!(urls, vec!["a", "b", "c"]);
+
+        assert!(index.find_by_url("b").is_some());
+
+        let reread = CdxIndex::read(Cursor::new(out)).unwrap();
+        assert_eq!(reread.entries.len(), 3);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod index;
+pub mod reader;
+pub mod types;
+pub mod writer_doc_avro;
@@ -0,0 +1,131 @@
+/*! Streaming WARC-to-[Document] reader
+Pulls [Document]s straight out of a WARC file, so callers don't have to wire
+up the [warc] crate themselves. Built on top of [warc::WarcReader]'s
+record-at-a-time iteration: each `response`/`conversion` record is paired
+with a [Metadata], while `warcinfo`/`request`/`metadata` records are skipped.
+
+Plain WARC input goes through [DocumentReader::new]; a WARC.gz file (a
+multi-member gzip stream, one member per record) needs
+[DocumentReader::new_gzip] instead — [WarcReader] itself does no gzip
+decoding, so handing it a compressed stream directly just yields framing
+errors.
+!*/
+// NOTE: assumes the `warc` crate exposes `RecordType: From<&str>` with
+// `Unknown(String)` as a tuple variant (no `FromStr`/unit `Unknown`), matching
+// the version already pulled in by `Document::from_record`. `flate2`,
+// `apache_avro` and `once_cell` (used here and in `writer_doc_avro`) need
+// adding to `Cargo.toml` alongside it — there's no manifest in this tree yet
+// to declare any of it.
+use std::io::{BufRead, BufReader, Read};
+
+use flate2::read::MultiGzDecoder;
+use warc::{RecordType, WarcHeader, WarcReader};
+
+use crate::common::Diagnostics;
+use crate::error::Error;
+use crate::v3::types::document::{Document, Metadata};
+
+/// Record types that carry a fetched body and should become [Document]s.
+fn is_content_record(record_type: &RecordType) -> bool {
+    matches!(record_type, RecordType::Response | RecordType::Conversion)
+}
+
+/// Iterator adapter turning the raw records of a WARC file into [Document]s.
+///
+/// Each yielded record is paired with a [Metadata]: either the one supplied
+/// via [DocumentReader::new_with_metadata], or [Metadata::default] otherwise.
+/// Malformed records (bad WARC framing, non-UTF8 headers) surface as
+/// `Err(`[Error]`)` rather than panicking; skipped record types (`warcinfo`,
+/// `request`, `metadata`) are simply not yielded.
+pub struct DocumentReader<R: BufRead> {
+    records: warc::RecordIter<R>,
+    metadata: Metadata,
+}
+
+impl<R: BufRead> DocumentReader<R> {
+    /// Build a reader over a plain (uncompressed) WARC stream, using
+    /// [Metadata::default] for every document.
+    pub fn new(inner: R) -> Self {
+        Self::new_with_metadata(inner, Metadata::default())
+    }
+
+    /// Build a reader over a plain (uncompressed) WARC stream, pairing every
+    /// document with `metadata`.
+    pub fn new_with_metadata(inner: R, metadata: Metadata) -> Self {
+        Self {
+            records: WarcReader::new(inner).iter_records(),
+            metadata,
+        }
+    }
+
+    /// Like [Iterator::next], which silently recovers a non-UTF8 body lossily,
+    /// but also reports that recovery (and any non-UTF8 header) in the
+    /// returned [Diagnostics] rather than as an [Error].
+    pub fn next_with_diagnostics(&mut self) -> Option<Result<(Document, Diagnostics), Error>> {
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(Error::Custom(e.to_string()))),
+            };
+
+            let record_type = record
+                .header(WarcHeader::WarcType)
+                .map(|t| RecordType::from(t.as_ref()))
+                .unwrap_or_else(|| RecordType::Unknown(String::new()));
+
+            if !is_content_record(&record_type) {
+                continue;
+            }
+
+            return Some(Ok(Document::from_record_with_diagnostics(
+                record,
+                self.metadata.clone(),
+            )));
+        }
+    }
+}
+
+impl<R: Read> DocumentReader<BufReader<MultiGzDecoder<R>>> {
+    /// Build a reader over a gzip-compressed (WARC.gz) stream, using
+    /// [Metadata::default] for every document.
+    ///
+    /// [MultiGzDecoder] transparently concatenates the decoded output of
+    /// every gzip member in the stream, which is exactly how WARC.gz files
+    /// are laid out (one member per record).
+    pub fn new_gzip(inner: R) -> Self {
+        Self::new(BufReader::new(MultiGzDecoder::new(inner)))
+    }
+
+    /// Build a reader over a gzip-compressed (WARC.gz) stream, pairing every
+    /// document with `metadata`.
+    pub fn new_gzip_with_metadata(inner: R, metadata: Metadata) -> Self {
+        Self::new_with_metadata(BufReader::new(MultiGzDecoder::new(inner)), metadata)
+    }
+}
+
+impl<R: BufRead> Iterator for DocumentReader<R> {
+    type Item = Result<Document, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(Error::Custom(e.to_string()))),
+            };
+
+            let record_type = record
+                .header(WarcHeader::WarcType)
+                .map(|t| RecordType::from(t.as_ref()))
+                .unwrap_or_else(|| RecordType::Unknown(String::new()));
+
+            if !is_content_record(&record_type) {
+                continue;
+            }
+
+            return Some(Ok(Document::from_record(
+                record,
+                self.metadata.clone(),
+            )));
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 
 use oxilangtag::LanguageTag;
+use schemars::JsonSchema;
 
 use serde::Deserialize;
 use serde::Serialize;
@@ -10,26 +11,57 @@ use warc::Record;
 use warc::WarcHeader;
 
 use crate::common::Identification as IdentificationGen;
+use crate::common::{DiagnosticEntry, Diagnostics};
+use crate::error::Error;
 
 type Identification = IdentificationGen<String>;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// Accepts either the current keyed-map form or the old flat array form of
+/// `quality_warnings`/`categories`, so JSONL written before the switch to
+/// per-source maps still reads back. A non-empty legacy array is filed under
+/// the `"legacy"` key.
+fn deserialize_tagged_map<'de, D>(deserializer: D) -> Result<HashMap<String, Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Form {
+        Map(HashMap<String, Vec<String>>),
+        List(Vec<String>),
+    }
+
+    Ok(match Option::<Form>::deserialize(deserializer)? {
+        None => HashMap::new(),
+        Some(Form::Map(map)) => map,
+        Some(Form::List(list)) if list.is_empty() => HashMap::new(),
+        Some(Form::List(list)) => {
+            let mut map = HashMap::new();
+            map.insert("legacy".to_string(), list);
+            map
+        }
+    })
+}
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 /// OSCAR-specific metadata
-// TODO: make it a HashMap
-// TODO: make annotation/categories hashmaps
 /// Contains document metadata:
 /// - `identification` is the document-level language identification (see [Identification])
 /// - `harmful_pp` is the perplexiry of the document, related to a model trained to recognize adult documents
-/// - `quality_warnings` (ex-annotation) contains tags for some length/content based quality filters
-/// - `categories` contains categories based on the url of the document. Uses the ut1 blocklist as a base.
+/// - `quality_warnings` (ex-annotation) contains tags for some length/content based quality filters, keyed by the annotator/filter that raised them (e.g. `"length-filter"`, `"adult-model"`)
+/// - `categories` contains categories based on the url of the document, keyed by their source (e.g. `"ut1"` for the ut1 blocklist)
 /// - `sentence_identifiations` contains line-level identifications.
+/// - `extra` holds arbitrary, pipeline-stage-specific metadata that hasn't been promoted to a typed field yet.
 pub struct Metadata {
     identification: Identification,
     harmful_pp: Option<f32>,
-    quality_warnings: Option<Vec<String>>,
-    categories: Option<Vec<String>>,
+    #[serde(deserialize_with = "deserialize_tagged_map", default)]
+    quality_warnings: HashMap<String, Vec<String>>,
+    #[serde(deserialize_with = "deserialize_tagged_map", default)]
+    categories: HashMap<String, Vec<String>>,
     sentence_identifications: Vec<Option<Identification>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    extra: HashMap<String, serde_json::Value>,
 }
 
 impl Metadata {
@@ -40,35 +72,49 @@ impl Metadata {
         Metadata {
             identification: identification.clone(),
             harmful_pp: None,
-            quality_warnings: None,
-            categories: None,
+            quality_warnings: HashMap::new(),
+            categories: HashMap::new(),
             sentence_identifications: sentence_identifications.to_owned(),
+            extra: HashMap::new(),
         }
     }
 
-    pub fn add_annotation(&mut self, annotation: String) {
-        match &mut self.quality_warnings {
-            Some(anno) => anno.push(annotation),
-            None => self.quality_warnings = Some(vec![annotation]),
-        }
+    /// Get a reference to the metadata's quality warnings, keyed by the source that raised them.
+    pub fn quality_warnings(&self) -> &HashMap<String, Vec<String>> {
+        &self.quality_warnings
     }
 
-    pub fn categories(&self) -> Option<&Vec<String>> {
-        self.categories.as_ref()
+    /// Get the quality warnings raised by a specific `source`, if any.
+    pub fn quality_warnings_from(&self, source: &str) -> Option<&[String]> {
+        self.quality_warnings.get(source).map(Vec::as_slice)
     }
-    pub fn add_category(&mut self, category: String) {
-        match &mut self.categories {
-            Some(cat) => cat.push(category),
-            None => self.categories = Some(vec![category]),
-        }
+
+    /// Append a quality warning raised by `source`.
+    pub fn add_quality_warning(&mut self, source: impl Into<String>, warning: String) {
+        self.quality_warnings
+            .entry(source.into())
+            .or_default()
+            .push(warning);
     }
-    pub fn set_categories(&mut self, categories: Option<Vec<String>>) {
-        self.categories = categories;
+
+    /// Get a reference to the metadata's categories, keyed by the source that assigned them.
+    pub fn categories(&self) -> &HashMap<String, Vec<String>> {
+        &self.categories
     }
 
-    /// Get a reference to the metadata's annotation.
-    pub fn annotation(&self) -> Option<&Vec<String>> {
-        self.quality_warnings.as_ref()
+    /// Get the categories assigned by a specific `source`, if any.
+    pub fn categories_from(&self, source: &str) -> Option<&[String]> {
+        self.categories.get(source).map(Vec::as_slice)
+    }
+
+    /// Append a category assigned by `source`.
+    pub fn add_category(&mut self, source: impl Into<String>, category: String) {
+        self.categories.entry(source.into()).or_default().push(category);
+    }
+
+    /// Replace all categories assigned by `source`.
+    pub fn set_categories(&mut self, source: impl Into<String>, categories: Vec<String>) {
+        self.categories.insert(source.into(), categories);
     }
 
     /// Get a reference to the metadata's sentence identifications.
@@ -79,6 +125,21 @@ impl Metadata {
     pub fn set_harmful_pp(&mut self, harmful_pp: Option<f32>) {
         self.harmful_pp = harmful_pp;
     }
+
+    /// Get a reference to the metadata's open-ended extra fields.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+
+    /// Get a single extra field by key.
+    pub fn get_extra(&self, key: &str) -> Option<&serde_json::Value> {
+        self.extra.get(key)
+    }
+
+    /// Set an extra field, for metadata that hasn't been promoted to a typed field yet.
+    pub fn set_extra(&mut self, key: impl Into<String>, value: serde_json::Value) {
+        self.extra.insert(key.into(), value);
+    }
 }
 
 impl Default for Metadata {
@@ -89,12 +150,13 @@ impl Default for Metadata {
         Self {
             identification: Identification::new(LanguageTag::parse("en".to_string()).unwrap(), 1.0),
             harmful_pp: None,
-            quality_warnings: None,
-            categories: None,
+            quality_warnings: HashMap::new(),
+            categories: HashMap::new(),
             sentence_identifications: vec![Some(Identification::new(
                 LanguageTag::parse("en".to_string()).unwrap(),
                 1.0,
             ))],
+            extra: HashMap::new(),
         }
     }
 }
@@ -123,10 +185,24 @@ struct DocumentSer {
     metadata: Metadata,
 }
 
+/// Schema-only stand-in for [DocumentSer].
+///
+/// [WarcHeader] is an external enum without a [JsonSchema] impl, but it
+/// serializes as its header name, so for schema purposes `warc_headers` is
+/// described as a plain string-keyed map instead.
+#[derive(JsonSchema)]
+#[schemars(rename = "Document")]
+#[allow(dead_code)]
+struct DocumentSchema {
+    content: String,
+    warc_headers: HashMap<String, String>,
+    metadata: Metadata,
+}
+
 impl DocumentSer {
-    // pub fn get_schema() -> Result<String, Error> {
-    //     serde_json::to_string_pretty(&schemars::schema_for!(Self)).map_err(Error::Serde)
-    // }
+    pub fn get_schema() -> Result<String, Error> {
+        serde_json::to_string_pretty(&schemars::schema_for!(DocumentSchema)).map_err(Error::Serde)
+    }
 }
 impl From<Document> for DocumentSer {
     fn from(d: Document) -> Self {
@@ -169,9 +245,12 @@ impl Document {
         }
     }
 
-    // pub fn get_schema() -> Result<String, Error> {
-    //     DocumentSer::get_schema()
-    // }
+    /// Pretty-printed JSON Schema describing the JSONL form of a [Document],
+    /// for validators, data-catalog tooling and typed loaders in other languages.
+    pub fn get_schema() -> Result<String, Error> {
+        DocumentSer::get_schema()
+    }
+
     /// Instantiate a Document from a record and a related metadata.
     pub fn from_record(record: Record<BufferedBody>, metadata: Metadata) -> Self {
         let (header, body) = record.into_raw_parts();
@@ -185,6 +264,53 @@ impl Document {
         }
     }
 
+    /// Like [Document::from_record], but reports bodies and headers that
+    /// weren't valid UTF-8 and had to be lossily recovered as recoverable
+    /// entries in the returned [Diagnostics], rather than failing silently.
+    ///
+    /// Note: unknown-language-tag diagnostics (from `Lang::from_str`) aren't
+    /// produced here — a WARC record carries no language tag of its own to
+    /// validate. That failure mode belongs to constructing an
+    /// [Identification](crate::common::Identification) from a raw label, and
+    /// is surfaced there as `Err(`[Error::UnknownLang]`)` for a caller to
+    /// fold into its own [Diagnostics].
+    pub fn from_record_with_diagnostics(
+        record: Record<BufferedBody>,
+        metadata: Metadata,
+    ) -> (Self, Diagnostics) {
+        let (header, body) = record.into_raw_parts();
+        let mut diagnostics = Diagnostics::new();
+
+        let content = match String::from_utf8(body) {
+            Ok(content) => content,
+            Err(e) => {
+                let recovered = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                diagnostics.push(DiagnosticEntry::recoverable(Error::from(
+                    e.utf8_error().to_string(),
+                )));
+                recovered
+            }
+        };
+
+        for (name, value) in &header.headers {
+            if let Err(e) = std::str::from_utf8(value) {
+                diagnostics.push(DiagnosticEntry::recoverable(Error::Custom(format!(
+                    "header {name:?} was not valid UTF-8, recovered lossily: {e}"
+                ))));
+            }
+        }
+        let warc_headers = header.headers;
+
+        (
+            Self {
+                content,
+                warc_headers,
+                metadata,
+            },
+            diagnostics,
+        )
+    }
+
     /// Get a reference to the Document's identification
     pub fn identification(&self) -> &Identification {
         &self.metadata.identification
@@ -287,4 +413,32 @@ mod tests {
 
         println!("{:?}", m2);
     }
+
+    #[test]
+    fn test_get_schema() {
+        let schema = Document::get_schema().unwrap();
+        assert!(schema.contains("\"title\": \"Document\""));
+        assert!(schema.contains("warc_headers"));
+    }
+
+    #[test]
+    fn test_metadata_reads_legacy_array_form() {
+        let legacy = r#"{
+            "identification": {"label": "en", "prob": 1.0},
+            "harmful_pp": null,
+            "quality_warnings": ["too_short"],
+            "categories": ["adult"],
+            "sentence_identifications": []
+        }"#;
+
+        let parsed: Metadata = serde_json::from_str(legacy).unwrap();
+        assert_eq!(
+            parsed.quality_warnings_from("legacy"),
+            Some(["too_short".to_string()].as_slice())
+        );
+        assert_eq!(
+            parsed.categories_from("legacy"),
+            Some(["adult".to_string()].as_slice())
+        );
+    }
 }
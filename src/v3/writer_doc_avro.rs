@@ -0,0 +1,193 @@
+/*! Avro serialization for [Document]
+Provides an Avro object-container-file counterpart to the line-delimited JSON
+representation of [Document]: a [DocWriter] appends documents one at a time
+using serde, and a [DocReader] iterates them back out. The schema is kept in
+a single static so that writer and reader agree on layout without a runtime
+handshake.
+!*/
+use std::io::{Read, Write};
+
+use apache_avro::{from_value, types::Value, Codec, Reader, Schema, Writer};
+use once_cell::sync::Lazy;
+
+use crate::error::Error;
+use crate::v3::types::document::Document;
+
+/// Avro record schema mirroring [Document] and its nested [Metadata](crate::v3::types::document::Metadata)
+/// and [Identification](crate::common::Identification).
+static DOCUMENT_SCHEMA_RAW: &str = r#"
+{
+    "type": "record",
+    "name": "Document",
+    "fields": [
+        {"name": "content", "type": "string"},
+        {"name": "warc_headers", "type": {"type": "map", "values": "string"}},
+        {
+            "name": "metadata",
+            "type": {
+                "type": "record",
+                "name": "Metadata",
+                "fields": [
+                    {
+                        "name": "identification",
+                        "type": {
+                            "type": "record",
+                            "name": "Identification",
+                            "fields": [
+                                {"name": "label", "type": "string"},
+                                {"name": "prob", "type": "float"}
+                            ]
+                        }
+                    },
+                    {"name": "harmful_pp", "type": ["null", "float"], "default": null},
+                    {
+                        "name": "quality_warnings",
+                        "type": {"type": "map", "values": {"type": "array", "items": "string"}},
+                        "default": {}
+                    },
+                    {
+                        "name": "categories",
+                        "type": {"type": "map", "values": {"type": "array", "items": "string"}},
+                        "default": {}
+                    },
+                    {
+                        "name": "sentence_identifications",
+                        "type": {"type": "array", "items": ["null", "Identification"]}
+                    }
+                ]
+            }
+        }
+    ]
+}
+"#;
+// NOTE: `Metadata::extra` (open `HashMap<String, serde_json::Value>`) isn't
+// represented here: Avro has no schemaless "any" type, so encoding it would
+// need a dedicated representation (e.g. JSON-stringified map values). Rather
+// than silently dropping it on write, `DocWriter::append` rejects documents
+// with a non-empty `extra`.
+
+/// Parsed, lazily-initialized [Schema] for [Document].
+pub static DOCUMENT_SCHEMA: Lazy<Schema> =
+    Lazy::new(|| Schema::parse_str(DOCUMENT_SCHEMA_RAW).expect("invalid Document Avro schema"));
+
+/// Appends [Document]s to an Avro object-container file.
+///
+/// Wraps an [apache_avro::Writer] bound to [DOCUMENT_SCHEMA], so callers never
+/// have to juggle the schema themselves.
+pub struct DocWriter<W: Write> {
+    writer: Writer<'static, W>,
+}
+
+impl<W: Write> DocWriter<W> {
+    /// Create a new writer, compressing blocks with `codec`.
+    pub fn new(inner: W, codec: Codec) -> Self {
+        Self {
+            writer: Writer::with_codec(&DOCUMENT_SCHEMA, inner, codec),
+        }
+    }
+
+    /// Serialize and append a single [Document].
+    ///
+    /// Returns [Error::Custom] if `document`'s metadata has a non-empty
+    /// `extra` map: [DOCUMENT_SCHEMA] has no field for it, so appending
+    /// would otherwise silently drop that data instead of writing it.
+    pub fn append(&mut self, document: &Document) -> Result<(), Error> {
+        if !document.metadata().extra().is_empty() {
+            return Err(Error::Custom(
+                "Avro writer does not support Metadata::extra; refusing to silently drop it"
+                    .to_string(),
+            ));
+        }
+
+        self.writer
+            .append_ser(document)
+            .map_err(Error::Avro)
+            .map(|_| ())
+    }
+
+    /// Append every [Document] yielded by `documents`.
+    pub fn extend<'a, I>(&mut self, documents: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Document>,
+    {
+        for document in documents {
+            self.append(document)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the current block and the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::Avro)?;
+        Ok(())
+    }
+
+    /// Consume the writer, returning the underlying [Write].
+    pub fn into_inner(self) -> Result<W, Error> {
+        self.writer.into_inner().map_err(Error::Avro)
+    }
+}
+
+/// Reads [Document]s back out of an Avro object-container file produced by [DocWriter].
+pub struct DocReader<R: Read> {
+    reader: Reader<'static, R>,
+}
+
+impl<R: Read> DocReader<R> {
+    /// Open an Avro object-container file for reading. The file's embedded
+    /// writer schema is resolved against [DOCUMENT_SCHEMA] as the reader schema.
+    pub fn new(inner: R) -> Result<Self, Error> {
+        let reader = Reader::with_schema(&DOCUMENT_SCHEMA, inner).map_err(Error::Avro)?;
+        Ok(Self { reader })
+    }
+}
+
+impl<R: Read> Iterator for DocReader<R> {
+    type Item = Result<Document, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value: Value = match self.reader.next()? {
+            Ok(value) => value,
+            Err(e) => return Some(Err(Error::Avro(e))),
+        };
+        Some(from_value::<Document>(&value).map_err(Error::Avro))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v3::types::document::Metadata;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_roundtrip() {
+        let document = Document::new(
+            "some content".to_string(),
+            Default::default(),
+            Metadata::default(),
+        );
+
+        let mut buf = Vec::new();
+        let mut writer = DocWriter::new(&mut buf, Codec::Null);
+        writer.append(&document).unwrap();
+        writer.flush().unwrap();
+
+        let reader = DocReader::new(Cursor::new(buf)).unwrap();
+        let documents: Vec<Document> = reader.map(Result::unwrap).collect();
+
+        assert_eq!(documents, vec![document]);
+    }
+
+    #[test]
+    fn test_append_rejects_non_empty_extra() {
+        let mut metadata = Metadata::default();
+        metadata.set_extra("ut1_category_version", serde_json::json!("2023-06"));
+
+        let document = Document::new("some content".to_string(), Default::default(), metadata);
+
+        let mut buf = Vec::new();
+        let mut writer = DocWriter::new(&mut buf, Codec::Null);
+        assert!(writer.append(&document).is_err());
+    }
+}